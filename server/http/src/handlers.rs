@@ -0,0 +1,209 @@
+use std::sync::Arc;
+
+use axum::extract::ws::WebSocketUpgrade;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{Html, IntoResponse, Response};
+use bytes::Bytes;
+use opentelemetry::trace::{FutureExt, Span, TraceContextExt};
+use prometheus::{Encoder, TextEncoder};
+
+use graph::prelude::{serde_json, GraphQlRunner};
+
+use crate::state::ServiceState;
+use crate::tracing::{estimate_query_complexity, extract_context, start_query_span};
+use crate::ws::{graphql_transport_ws, legacy, WS_PROTOCOL_GRAPHQL_TRANSPORT_WS, WS_PROTOCOL_LEGACY};
+
+/// Minimal, self-contained GraphiQL IDE that posts queries to whatever path
+/// it's served from. Since `POST /` needs a `deploymentId` in the body, the
+/// page adds the one named by its own `?deploymentId=` query parameter.
+const GRAPHIQL_HTML: &str = include_str!("graphiql.html");
+
+/// `GET /`: serves the GraphiQL IDE, wired up to query this same endpoint.
+pub(crate) async fn graphiql<Q>() -> impl IntoResponse {
+    Html(GRAPHIQL_HTML)
+}
+
+/// `GET /metrics`: renders the process's Prometheus registry in the text
+/// exposition format so it can be scraped directly.
+pub(crate) async fn metrics<Q>(State(state): State<Arc<ServiceState<Q>>>) -> Response {
+    let metric_families = state.metrics.gather();
+    let mut buffer = Vec::new();
+    let encoder = TextEncoder::new();
+    if let Err(e) = encoder.encode(&metric_families, &mut buffer) {
+        graph::slog::error!(state.logger, "Failed to encode metrics"; "error" => e.to_string());
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to encode metrics").into_response();
+    }
+
+    ([(axum::http::header::CONTENT_TYPE, encoder.format_type())], Bytes::from(buffer)).into_response()
+}
+
+/// Parses a raw request body as a GraphQL-over-HTTP JSON payload, returning
+/// a ready-to-send 400 response on failure.
+fn parse_payload(body: &Bytes) -> Result<serde_json::Value, Response> {
+    serde_json::from_slice(body)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid request body: {}", e)).into_response())
+}
+
+/// Executes a GraphQL query against `deployment_id` inside a span that's a
+/// child of the caller's `traceparent`, if any, and records request count,
+/// latency and bytes-served metrics. Shared by the `/` and
+/// `/subgraphs/id/:deployment_id` routes, which differ only in how they
+/// resolve `deployment_id`.
+async fn execute_graphql<Q: GraphQlRunner>(
+    state: &Arc<ServiceState<Q>>,
+    deployment_id: String,
+    headers: HeaderMap,
+    payload: serde_json::Value,
+) -> Response {
+    let metrics = &state.metrics;
+    let parent_cx = extract_context(&headers);
+
+    metrics
+        .requests_in_flight
+        .with_label_values(&[&deployment_id])
+        .inc();
+    let timer = metrics
+        .query_execution_time
+        .with_label_values(&[&deployment_id])
+        .start_timer();
+
+    let operation_name = payload
+        .get("operationName")
+        .and_then(|v| v.as_str())
+        .map(str::to_owned);
+    let query_complexity = payload
+        .get("query")
+        .and_then(|v| v.as_str())
+        .map(estimate_query_complexity);
+
+    // Attaching `cx` as a thread-local guard across the `.await` below would
+    // be silently dropped if tokio resumes this task on a different worker
+    // thread after an internal await inside `run_query_payload`. Carry the
+    // context through the future explicitly instead.
+    let cx = start_query_span(
+        &parent_cx,
+        &deployment_id,
+        operation_name.as_deref(),
+        query_complexity,
+    );
+
+    let result = state
+        .graphql_runner
+        .run_query_payload(deployment_id.clone(), payload)
+        .with_context(cx.clone())
+        .await;
+
+    timer.observe_duration();
+    metrics
+        .requests_in_flight
+        .with_label_values(&[&deployment_id])
+        .dec();
+
+    let response = match result {
+        Ok(result) => {
+            metrics
+                .request_count
+                .with_label_values(&[&deployment_id, "200"])
+                .inc();
+            Bytes::from(result.to_string()).into_response()
+        }
+        Err(e) => {
+            graph::slog::error!(state.logger, "Query execution failed"; "error" => e.to_string());
+            cx.span().record_error(e.as_ref());
+            metrics
+                .request_count
+                .with_label_values(&[&deployment_id, "500"])
+                .inc();
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Bytes::from(format!("{{\"errors\":[{{\"message\":\"{}\"}}]}}", e)),
+            )
+                .into_response()
+        }
+    };
+
+    metrics
+        .bytes_served
+        .with_label_values(&[&deployment_id])
+        .inc_by(response.body().size_hint().lower());
+
+    response
+}
+
+/// `POST /subgraphs/id/:deployment_id`: runs a query against the named
+/// deployment.
+pub(crate) async fn graphql_query<Q: GraphQlRunner>(
+    State(state): State<Arc<ServiceState<Q>>>,
+    Path(deployment_id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let payload = match parse_payload(&body) {
+        Ok(payload) => payload,
+        Err(response) => return response,
+    };
+    execute_graphql(&state, deployment_id, headers, payload).await
+}
+
+/// `POST /`: runs a query against the deployment named by the request
+/// body's top-level `deploymentId` field. There's no path segment here to
+/// carry an id, and a `NodeId` isn't a substitute for one — it identifies
+/// this node's indexer assignment, and a single node is commonly assigned
+/// many deployments, so it never resolves to a real deployment hash.
+/// Callers must name a deployment explicitly, the same way they would via
+/// `/subgraphs/id/:deployment_id`.
+pub(crate) async fn graphql_query_root<Q: GraphQlRunner>(
+    State(state): State<Arc<ServiceState<Q>>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let payload = match parse_payload(&body) {
+        Ok(payload) => payload,
+        Err(response) => return response,
+    };
+
+    let deployment_id = match payload.get("deploymentId").and_then(|v| v.as_str()) {
+        Some(id) => id.to_owned(),
+        None => {
+            return (
+                StatusCode::BAD_REQUEST,
+                "Missing \"deploymentId\": POST / has no path segment to carry a deployment \
+                 id, so the request body must name one explicitly, e.g. \
+                 {\"deploymentId\": \"Qm...\", \"query\": \"...\"}",
+            )
+                .into_response();
+        }
+    };
+
+    execute_graphql(&state, deployment_id, headers, payload).await
+}
+
+/// `GET /subscriptions` with a WebSocket upgrade: chooses the subscription
+/// codec based on the negotiated `Sec-WebSocket-Protocol`.
+pub(crate) async fn websocket<Q: GraphQlRunner>(
+    State(state): State<Arc<ServiceState<Q>>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    // `selected_protocol` is only set once `protocols` has matched the
+    // client's requested subprotocols against ours.
+    let ws = ws.protocols([WS_PROTOCOL_LEGACY, WS_PROTOCOL_GRAPHQL_TRANSPORT_WS]);
+    let use_graphql_transport_ws = ws
+        .selected_protocol()
+        .map(|p| p == WS_PROTOCOL_GRAPHQL_TRANSPORT_WS)
+        .unwrap_or(false);
+
+    let logger = state.logger.clone();
+    let graphql_runner = state.graphql_runner.clone();
+    let metrics = state.metrics.clone();
+
+    ws.on_upgrade(move |socket| async move {
+            metrics.ws_subscriptions.inc();
+            if use_graphql_transport_ws {
+                graphql_transport_ws::serve(logger, graphql_runner, socket).await;
+            } else {
+                legacy::serve(logger, graphql_runner, socket).await;
+            }
+            metrics.ws_subscriptions.dec();
+        })
+}
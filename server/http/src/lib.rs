@@ -0,0 +1,12 @@
+mod handlers;
+mod metrics;
+mod router;
+mod server;
+mod state;
+mod status;
+mod tracing;
+mod ws;
+
+pub use crate::router::HttpEndpoint;
+pub use crate::server::{GraphQLServeError, GraphQLServer, ListenAddr};
+pub use crate::tracing::{init_tracer, OTLP_ENDPOINT_ENV_VAR};
@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use graph::prelude::{Gauge, GaugeVec, HistogramVec, IntCounterVec, MetricsRegistry};
+
+/// Per-request metrics for the GraphQL HTTP server, registered in the
+/// shared `MetricsRegistry` at construction (mirroring how the component
+/// logger is built from `LoggerFactory`).
+pub struct ServerMetrics {
+    registry: Arc<dyn MetricsRegistry>,
+    pub request_count: Box<IntCounterVec>,
+    pub query_execution_time: Box<HistogramVec>,
+    pub requests_in_flight: Box<GaugeVec>,
+    pub ws_subscriptions: Box<Gauge>,
+    pub bytes_served: Box<IntCounterVec>,
+}
+
+impl ServerMetrics {
+    pub fn new(registry: Arc<dyn MetricsRegistry>) -> Self {
+        let request_count = registry
+            .new_int_counter_vec(
+                "graphql_server_request_count",
+                "Number of GraphQL HTTP requests, labeled by status",
+                &["deployment", "status"],
+            )
+            .expect("failed to register graphql_server_request_count");
+
+        let query_execution_time = registry
+            .new_histogram_vec(
+                "graphql_server_query_execution_time",
+                "GraphQL query execution time in seconds",
+                &["deployment"],
+                vec![0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0],
+            )
+            .expect("failed to register graphql_server_query_execution_time");
+
+        let requests_in_flight = registry
+            .new_gauge_vec(
+                "graphql_server_requests_in_flight",
+                "Number of GraphQL HTTP requests currently being served",
+                &["deployment"],
+            )
+            .expect("failed to register graphql_server_requests_in_flight");
+
+        let ws_subscriptions = registry
+            .new_gauge(
+                "graphql_server_ws_subscriptions",
+                "Number of open GraphQL WebSocket subscription connections; each connection may carry several subscriptions",
+                vec![],
+            )
+            .expect("failed to register graphql_server_ws_subscriptions");
+
+        let bytes_served = registry
+            .new_int_counter_vec(
+                "graphql_server_bytes_served",
+                "Bytes served in GraphQL HTTP responses, labeled by deployment",
+                &["deployment"],
+            )
+            .expect("failed to register graphql_server_bytes_served");
+
+        ServerMetrics {
+            registry,
+            request_count,
+            query_execution_time,
+            requests_in_flight,
+            ws_subscriptions,
+            bytes_served,
+        }
+    }
+
+    /// Gathers the current value of every metric registered through the
+    /// injected `MetricsRegistry`, for rendering on `/metrics`. Deliberately
+    /// not `prometheus::gather()`, which only sees the process-global
+    /// default registry rather than the one graph-node actually wired up.
+    pub fn gather(&self) -> Vec<prometheus::proto::MetricFamily> {
+        self.registry.gather()
+    }
+}
@@ -0,0 +1,12 @@
+use axum::Router;
+
+/// Lets an application (graph-node itself, or an embedder) contribute extra
+/// HTTP routes — health checks, status, cost APIs, admin endpoints — that
+/// get merged into the same server and port as the GraphQL endpoint,
+/// instead of spinning up a separate `Server` per API.
+pub trait HttpEndpoint {
+    /// Builds the `Router` for this endpoint. It's merged into the
+    /// server's router with [`axum::Router::merge`], so routes must not
+    /// overlap with the built-in GraphQL routes or with each other.
+    fn router(&self) -> Router;
+}
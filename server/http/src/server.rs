@@ -1,36 +1,68 @@
+use std::fs;
 use std::net::{Ipv4Addr, SocketAddrV4};
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
 use std::sync::Arc;
 
-use futures::future::Future;
-use graph::anyhow;
+use axum::routing::{get, post};
+use axum::Router;
 use graph::log::factory::{ComponentLoggerConfig, ElasticComponentLoggerConfig};
-use graph::prelude::TryFutureExt;
 use graph::slog::{error, info};
-use hyper::service::make_service_fn;
-use hyper::Server;
+use hyperlocal::UnixServerExt;
 
-use crate::service::GraphQLService;
+use crate::handlers;
+use crate::metrics::ServerMetrics;
+use crate::router::HttpEndpoint;
+use crate::state::ServiceState;
+use crate::status;
 use graph::prelude::{
-    futures03, thiserror, thiserror::Error, GraphQlRunner, Logger, LoggerFactory, NodeId,
+    thiserror, thiserror::Error, GraphQlRunner, Logger, LoggerFactory, MetricsRegistry, NodeId,
 };
 
+/// The address a `GraphQLServer` should listen on: either a TCP port or the
+/// path to a Unix domain socket.
+#[derive(Clone, Debug)]
+pub enum ListenAddr {
+    Tcp(u16),
+    Unix(PathBuf),
+}
+
+impl std::fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ListenAddr::Tcp(port) => write!(f, "http://localhost:{}", port),
+            ListenAddr::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
 /// Errors that may occur when starting the server.
 #[derive(Debug, Error)]
 pub enum GraphQLServeError {
     #[error("Bind error: {0}")]
     BindError(#[from] hyper::Error),
+    #[error("Unix socket error: {0}")]
+    UnixSocketError(#[from] std::io::Error),
 }
 
-/// A GraphQL server based on Hyper.
+/// A GraphQL server based on an axum `Router`, so embedding applications can
+/// register extra routes alongside the built-in GraphQL endpoint.
 pub struct GraphQLServer<Q> {
     logger: Logger,
     graphql_runner: Arc<Q>,
     node_id: NodeId,
+    metrics: Arc<ServerMetrics>,
+    extra_routers: Vec<Router>,
 }
 
 impl<Q: GraphQlRunner> GraphQLServer<Q> {
     /// Creates a new GraphQL server.
-    pub fn new(logger_factory: &LoggerFactory, graphql_runner: Arc<Q>, node_id: NodeId) -> Self {
+    pub fn new(
+        logger_factory: &LoggerFactory,
+        metrics_registry: Arc<dyn MetricsRegistry>,
+        graphql_runner: Arc<Q>,
+        node_id: NodeId,
+    ) -> Self {
         let logger = logger_factory.component_logger(
             "GraphQLServer",
             Some(ComponentLoggerConfig {
@@ -39,48 +71,87 @@ impl<Q: GraphQlRunner> GraphQLServer<Q> {
                 }),
             }),
         );
+        let metrics = Arc::new(ServerMetrics::new(metrics_registry));
         GraphQLServer {
             logger,
             graphql_runner,
             node_id,
+            metrics,
+            extra_routers: Vec::new(),
+        }
+    }
+
+    /// Registers an extra HTTP endpoint (health, status, cost, admin, ...)
+    /// to be merged into the server's router before it's served.
+    pub fn with_endpoint(mut self, endpoint: &dyn HttpEndpoint) -> Self {
+        self.extra_routers.push(endpoint.router());
+        self
+    }
+
+    fn router(&self) -> Router {
+        let state = Arc::new(ServiceState {
+            logger: self.logger.clone(),
+            graphql_runner: self.graphql_runner.clone(),
+            node_id: self.node_id.clone(),
+            metrics: self.metrics.clone(),
+        });
+
+        let mut router = Router::new()
+            .route("/", get(handlers::graphiql::<Q>).post(handlers::graphql_query_root::<Q>))
+            .route("/subgraphs/id/:deployment_id", post(handlers::graphql_query::<Q>))
+            .route("/subscriptions", get(handlers::websocket::<Q>))
+            .route("/metrics", get(handlers::metrics::<Q>))
+            .route("/deployments/:id/health", get(status::deployment_health::<Q>))
+            .route("/status", get(status::status::<Q>))
+            .with_state(state);
+
+        for extra in &self.extra_routers {
+            router = router.merge(extra.clone());
         }
+
+        router
     }
 
-    pub fn serve(
-        &mut self,
-        port: u16,
-        ws_port: u16,
-    ) -> Result<Box<dyn Future<Item = (), Error = ()> + Send>, GraphQLServeError> {
+    /// Starts the server, listening on `listen_addr`. Subscriptions are
+    /// served on the same router at `/subscriptions`, so there's no
+    /// separate WebSocket port to advertise.
+    pub async fn serve(&mut self, listen_addr: ListenAddr) -> Result<(), GraphQLServeError> {
         let logger = self.logger.clone();
 
-        info!(
-            logger,
-            "Starting GraphQL HTTP server at: http://localhost:{}", port
-        );
+        info!(logger, "Starting GraphQL server at: {}", listen_addr; "node_id" => self.node_id.to_string());
 
-        let addr = SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 0), port);
-
-        // On every incoming request, launch a new GraphQL service that writes
-        // incoming queries to the query sink.
-        let logger_for_service = self.logger.clone();
-        let graphql_runner = self.graphql_runner.clone();
-        let node_id = self.node_id.clone();
-        let new_service = make_service_fn(move |_| {
-            let graphql_service = GraphQLService::new(
-                logger_for_service.clone(),
-                graphql_runner.clone(),
-                ws_port,
-                node_id.clone(),
-            );
-
-            futures03::future::ok::<_, anyhow::Error>(graphql_service)
-        });
+        let router = self.router();
+        let make_service = router.into_make_service();
+
+        match listen_addr {
+            ListenAddr::Tcp(port) => {
+                let addr = SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 0), port);
+                if let Err(e) = hyper::Server::try_bind(&addr.into())?
+                    .serve(make_service)
+                    .await
+                {
+                    error!(logger, "Server error"; "error" => format!("{}", e));
+                }
+            }
+            ListenAddr::Unix(path) => {
+                // Remove a stale socket file left behind by a previous run so
+                // `bind_unix` doesn't fail with `AddrInUse`.
+                if path.exists() {
+                    fs::remove_file(&path)?;
+                }
+
+                let server = hyper::Server::bind_unix(&path)?.serve(make_service);
 
-        // Create a task to run the server and handle HTTP requests
-        let task = Server::try_bind(&addr.into())?
-            .serve(new_service)
-            .map_err(move |e| error!(logger, "Server error"; "error" => format!("{}", e)));
+                // Restrict the socket to the owner; it's meant to be shared
+                // with a local reverse proxy or sidecar, not the world.
+                fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+
+                if let Err(e) = server.await {
+                    error!(logger, "Server error"; "error" => format!("{}", e));
+                }
+            }
+        }
 
-        Ok(Box::new(task.compat()))
+        Ok(())
     }
 }
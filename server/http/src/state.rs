@@ -0,0 +1,13 @@
+use std::sync::Arc;
+
+use graph::prelude::{GraphQlRunner, Logger, NodeId};
+
+use crate::metrics::ServerMetrics;
+
+/// Shared state handed to every axum handler via `State`.
+pub(crate) struct ServiceState<Q> {
+    pub logger: Logger,
+    pub graphql_runner: Arc<Q>,
+    pub node_id: NodeId,
+    pub metrics: Arc<ServerMetrics>,
+}
@@ -0,0 +1,171 @@
+//! Lightweight REST wrappers around the indexing-status resolver, so load
+//! balancers and uptime probes can check liveness/readiness without issuing
+//! a GraphQL query.
+
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+
+use graph::prelude::{serde_json, GraphQlRunner, StoreError};
+
+use crate::state::ServiceState;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct StatusQuery {
+    /// Comma-separated list of deployment ids to restrict the report to.
+    /// When omitted, all deployments known to the node are reported.
+    deployments: Option<String>,
+}
+
+/// Shapes the JSON body for `GET /deployments/:id/health`, kept separate
+/// from the handler so the shape can be unit tested without a resolver.
+fn health_json(
+    health: &str,
+    latest_block: Option<i64>,
+    earliest_block: Option<i64>,
+    chain_head_block: Option<i64>,
+) -> serde_json::Value {
+    serde_json::json!({
+        "health": health,
+        "latestBlock": latest_block,
+        "earliestBlock": earliest_block,
+        "chainHeadBlock": chain_head_block,
+    })
+}
+
+/// Shapes one entry of the JSON array for `GET /status`, kept separate
+/// from the handler so the shape can be unit tested without a resolver.
+fn status_entry_json(
+    deployment_id: &str,
+    synced: bool,
+    blocks_behind: i64,
+    fatal_error: Option<&str>,
+) -> serde_json::Value {
+    serde_json::json!({
+        "subgraph": deployment_id,
+        "synced": synced,
+        "blocksBehind": blocks_behind,
+        "fatalError": fatal_error,
+    })
+}
+
+/// `GET /deployments/:id/health`: healthy/unhealthy/failed plus the
+/// latest/earliest/chain-head block numbers for a single deployment.
+pub(crate) async fn deployment_health<Q: GraphQlRunner>(
+    State(state): State<Arc<ServiceState<Q>>>,
+    Path(deployment_id): Path<String>,
+) -> Response {
+    match state.graphql_runner.indexing_status(&deployment_id).await {
+        Ok(status) => health_json(
+            &status.health,
+            status.latest_block,
+            status.earliest_block,
+            status.chain_head_block,
+        )
+        .to_string()
+        .into_response(),
+        Err(e) => {
+            // Only a deployment that genuinely doesn't exist should read as
+            // "not found" to a load balancer or uptime probe — anything
+            // else (a transient store error, say) is an outage and must
+            // surface as a 5xx, or monitoring built on this endpoint won't
+            // see it.
+            if matches!(e.downcast_ref::<StoreError>(), Some(StoreError::DeploymentNotFound(_))) {
+                graph::slog::warn!(state.logger, "Unknown deployment"; "deployment" => deployment_id.clone());
+                (StatusCode::NOT_FOUND, format!("Unknown deployment: {}", deployment_id)).into_response()
+            } else {
+                graph::slog::error!(state.logger, "Failed to resolve deployment health"; "deployment" => deployment_id.clone(), "error" => e.to_string());
+                (StatusCode::SERVICE_UNAVAILABLE, "Failed to resolve deployment health").into_response()
+            }
+        }
+    }
+}
+
+/// `GET /status`: synced flag, blocks-behind and fatal error (if any) for
+/// all deployments, or a subset selected via `?deployments=a,b,c`.
+pub(crate) async fn status<Q: GraphQlRunner>(
+    State(state): State<Arc<ServiceState<Q>>>,
+    Query(params): Query<StatusQuery>,
+) -> Response {
+    let deployment_ids: Option<Vec<String>> = params
+        .deployments
+        .as_deref()
+        .map(|ids| ids.split(',').map(str::to_owned).collect());
+
+    match state.graphql_runner.indexing_statuses(deployment_ids).await {
+        Ok(statuses) => {
+            let body: Vec<_> = statuses
+                .into_iter()
+                .map(|status| {
+                    status_entry_json(
+                        &status.deployment_id,
+                        status.synced,
+                        status.blocks_behind,
+                        status.fatal_error.as_deref(),
+                    )
+                })
+                .collect();
+            serde_json::Value::Array(body).to_string().into_response()
+        }
+        Err(e) => {
+            graph::slog::warn!(state.logger, "Failed to resolve indexing status"; "error" => e.to_string());
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to resolve indexing status").into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn health_json_shapes_camel_case_block_fields() {
+        let value = health_json("healthy", Some(10), Some(1), Some(12));
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "health": "healthy",
+                "latestBlock": 10,
+                "earliestBlock": 1,
+                "chainHeadBlock": 12,
+            })
+        );
+    }
+
+    #[test]
+    fn health_json_allows_missing_block_numbers() {
+        let value = health_json("failed", None, None, None);
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "health": "failed",
+                "latestBlock": null,
+                "earliestBlock": null,
+                "chainHeadBlock": null,
+            })
+        );
+    }
+
+    #[test]
+    fn status_entry_json_shapes_camel_case_fields() {
+        let value = status_entry_json("QmDeployment", false, 42, Some("boom"));
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "subgraph": "QmDeployment",
+                "synced": false,
+                "blocksBehind": 42,
+                "fatalError": "boom",
+            })
+        );
+    }
+
+    #[test]
+    fn status_entry_json_sets_fatal_error_null_when_none() {
+        let value = status_entry_json("QmDeployment", true, 0, None);
+        assert_eq!(value["fatalError"], serde_json::Value::Null);
+    }
+}
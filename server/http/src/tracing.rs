@@ -0,0 +1,94 @@
+//! Distributed-tracing support for the GraphQL HTTP server: extracts W3C
+//! `traceparent`/`tracestate` headers from incoming requests and exports
+//! spans over OTLP so they stitch together with upstream gateway traces.
+
+use hyper::{header::HeaderValue, HeaderMap};
+use opentelemetry::global;
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::sdk::propagation::TraceContextPropagator;
+use opentelemetry::sdk::trace::Tracer;
+use opentelemetry::trace::{SpanKind, TraceContextExt, Tracer as _};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_http::HeaderExtractor;
+
+/// Env var pointing at the OTLP collector endpoint, e.g.
+/// `http://localhost:4317`. Tracing is disabled when unset.
+pub const OTLP_ENDPOINT_ENV_VAR: &str = "GRAPH_NODE_OTLP_ENDPOINT";
+
+/// Initializes the global OTLP exporter, if `GRAPH_NODE_OTLP_ENDPOINT` is
+/// set. Returns `None` when tracing is disabled.
+pub fn init_tracer() -> Option<Tracer> {
+    let endpoint = std::env::var(OTLP_ENDPOINT_ENV_VAR).ok()?;
+
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint),
+        )
+        .install_batch(opentelemetry::runtime::Tokio)
+        .ok()
+}
+
+/// Extracts the W3C trace context (`traceparent`/`tracestate`) carried on
+/// `headers`, falling back to a fresh root context if none is present.
+pub fn extract_context(headers: &HeaderMap<HeaderValue>) -> Context {
+    TraceContextPropagator::new().extract(&HeaderExtractor(headers))
+}
+
+/// Rough complexity heuristic for the `graph.query_complexity` span
+/// attribute: counts selection-set braces as a stand-in for the number of
+/// fields requested. A real cost analysis needs a parsed GraphQL AST, which
+/// isn't available at this layer — this is only meant to rank queries by
+/// relative weight in traces until complexity is computed further down the
+/// pipeline and threaded back up here.
+pub fn estimate_query_complexity(query: &str) -> u64 {
+    query.matches('{').count() as u64
+}
+
+/// Starts a span for a single GraphQL query, parented to `parent_cx`, and
+/// tags it with the attributes operators need to break down latency by
+/// deployment, operation and query weight.
+pub fn start_query_span(
+    parent_cx: &Context,
+    deployment_id: &str,
+    operation_name: Option<&str>,
+    query_complexity: Option<u64>,
+) -> Context {
+    let tracer = global::tracer("graph-server-http");
+    let mut attributes = vec![KeyValue::new("graph.deployment_id", deployment_id.to_string())];
+    if let Some(name) = operation_name {
+        attributes.push(KeyValue::new("graph.operation_name", name.to_string()));
+    }
+    if let Some(complexity) = query_complexity {
+        attributes.push(KeyValue::new("graph.query_complexity", complexity as i64));
+    }
+
+    let span = tracer
+        .span_builder("graphql_query")
+        .with_kind(SpanKind::Server)
+        .with_attributes(attributes)
+        .start_with_context(&tracer, parent_cx);
+
+    parent_cx.with_span(span)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_query_complexity_counts_selection_braces() {
+        assert_eq!(estimate_query_complexity("{ a b c }"), 1);
+        assert_eq!(
+            estimate_query_complexity("{ a { b { c } } }"),
+            3
+        );
+    }
+
+    #[test]
+    fn estimate_query_complexity_of_empty_query_is_zero() {
+        assert_eq!(estimate_query_complexity(""), 0);
+    }
+}
@@ -0,0 +1,202 @@
+//! Implements the `graphql-transport-ws` protocol used by `graphql-ws`,
+//! `urql` and Apollo Client v3.
+
+use std::sync::Arc;
+
+use axum::extract::ws::{Message, WebSocket};
+use futures03::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use graph::prelude::{futures03, serde_json, GraphQlRunner, Logger};
+use graph::slog::debug;
+
+use super::SubscriptionTable;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "PascalCase")]
+enum ClientMessage {
+    ConnectionInit { payload: Option<serde_json::Value> },
+    Subscribe { id: String, payload: serde_json::Value },
+    Complete { id: String },
+    Ping,
+    Pong,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "PascalCase")]
+enum ServerMessage {
+    ConnectionAck,
+    Next { id: String, payload: serde_json::Value },
+    Error { id: String, payload: Vec<serde_json::Value> },
+    Complete { id: String },
+    Pong,
+}
+
+fn encode_next(id: &str, payload: serde_json::Value) -> Message {
+    Message::Text(
+        serde_json::to_string(&ServerMessage::Next {
+            id: id.to_owned(),
+            payload,
+        })
+        .unwrap(),
+    )
+}
+
+fn encode_error(id: &str, message: String) -> Message {
+    Message::Text(
+        serde_json::to_string(&ServerMessage::Error {
+            id: id.to_owned(),
+            payload: vec![serde_json::json!({ "message": message })],
+        })
+        .unwrap(),
+    )
+}
+
+fn encode_complete(id: &str) -> Message {
+    Message::Text(serde_json::to_string(&ServerMessage::Complete { id: id.to_owned() }).unwrap())
+}
+
+/// Drives a single connection using the `graphql-transport-ws` message set
+/// (`ConnectionInit`/`ConnectionAck`, `Subscribe`/`Next`/`Complete`,
+/// `Ping`/`Pong`). Each `Subscribe` spawns its own task streaming `Next`
+/// frames until the underlying subscription ends or the client sends
+/// `Complete` for that id, so one connection can multiplex several live
+/// subscriptions.
+pub(crate) async fn serve<Q>(logger: Logger, graphql_runner: Arc<Q>, ws: WebSocket)
+where
+    Q: GraphQlRunner,
+{
+    let (mut sink, mut stream) = ws.split();
+    let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+
+    // Subscription tasks can't share `sink` directly, so they push frames
+    // onto this channel and a single task forwards them in order.
+    let forward = tokio::spawn(async move {
+        while let Some(msg) = rx.recv().await {
+            if sink.send(msg).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut subscriptions = SubscriptionTable::new();
+
+    while let Some(Ok(msg)) = stream.next().await {
+        let text = match msg {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+
+        let client_msg: ClientMessage = match serde_json::from_str(&text) {
+            Ok(msg) => msg,
+            Err(e) => {
+                debug!(logger, "Failed to parse graphql-transport-ws message"; "error" => e.to_string());
+                continue;
+            }
+        };
+
+        match client_msg {
+            ClientMessage::ConnectionInit { .. } => {
+                let ack = serde_json::to_string(&ServerMessage::ConnectionAck).unwrap();
+                if tx.send(Message::Text(ack)).is_err() {
+                    break;
+                }
+            }
+            ClientMessage::Subscribe { id, payload } => {
+                subscriptions.spawn(
+                    graphql_runner.clone(),
+                    logger.clone(),
+                    id,
+                    payload,
+                    tx.clone(),
+                    encode_next,
+                    encode_error,
+                    encode_complete,
+                );
+            }
+            ClientMessage::Complete { id } => subscriptions.cancel(&id),
+            ClientMessage::Ping => {
+                let pong = serde_json::to_string(&ServerMessage::Pong).unwrap();
+                if tx.send(Message::Text(pong)).is_err() {
+                    break;
+                }
+            }
+            ClientMessage::Pong => {}
+        }
+    }
+
+    drop(subscriptions);
+    drop(tx);
+    let _ = forward.await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_subscribe_message() {
+        let json = r#"{"type":"Subscribe","id":"1","payload":{"query":"subscription { x }"}}"#;
+        let msg: ClientMessage = serde_json::from_str(json).unwrap();
+        match msg {
+            ClientMessage::Subscribe { id, payload } => {
+                assert_eq!(id, "1");
+                assert_eq!(payload["query"], "subscription { x }");
+            }
+            other => panic!("expected Subscribe, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_complete_and_ping_and_pong() {
+        assert!(matches!(
+            serde_json::from_str::<ClientMessage>(r#"{"type":"Complete","id":"1"}"#).unwrap(),
+            ClientMessage::Complete { id } if id == "1"
+        ));
+        assert!(matches!(
+            serde_json::from_str::<ClientMessage>(r#"{"type":"Ping"}"#).unwrap(),
+            ClientMessage::Ping
+        ));
+        assert!(matches!(
+            serde_json::from_str::<ClientMessage>(r#"{"type":"Pong"}"#).unwrap(),
+            ClientMessage::Pong
+        ));
+    }
+
+    #[test]
+    fn encodes_connection_ack_and_next_and_complete_and_pong() {
+        let ack = serde_json::to_value(&ServerMessage::ConnectionAck).unwrap();
+        assert_eq!(ack, serde_json::json!({ "type": "ConnectionAck" }));
+
+        let next = serde_json::to_value(&ServerMessage::Next {
+            id: "1".to_owned(),
+            payload: serde_json::json!({ "data": { "x": 1 } }),
+        })
+        .unwrap();
+        assert_eq!(
+            next,
+            serde_json::json!({ "type": "Next", "id": "1", "payload": { "data": { "x": 1 } } })
+        );
+
+        let complete = serde_json::to_value(&ServerMessage::Complete { id: "1".to_owned() }).unwrap();
+        assert_eq!(complete, serde_json::json!({ "type": "Complete", "id": "1" }));
+
+        let pong = serde_json::to_value(&ServerMessage::Pong).unwrap();
+        assert_eq!(pong, serde_json::json!({ "type": "Pong" }));
+    }
+
+    #[test]
+    fn encodes_error() {
+        let error = serde_json::to_value(&ServerMessage::Error {
+            id: "1".to_owned(),
+            payload: vec![serde_json::json!({ "message": "boom" })],
+        })
+        .unwrap();
+        assert_eq!(
+            error,
+            serde_json::json!({ "type": "Error", "id": "1", "payload": [{ "message": "boom" }] })
+        );
+    }
+}
@@ -0,0 +1,111 @@
+pub(crate) mod graphql_transport_ws;
+pub(crate) mod legacy;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::extract::ws::Message;
+use futures03::StreamExt;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use graph::prelude::{futures03, serde_json, GraphQlRunner, Logger};
+use graph::slog::warn;
+
+/// Subprotocol name for the legacy `subscriptions-transport-ws` protocol.
+pub const WS_PROTOCOL_LEGACY: &str = "graphql-ws";
+
+/// Subprotocol name for the newer `graphql-transport-ws` protocol
+/// (implemented by `graphql-ws`, `urql` and Apollo Client v3).
+pub const WS_PROTOCOL_GRAPHQL_TRANSPORT_WS: &str = "graphql-transport-ws";
+
+/// Tracks the live per-subscription tasks for one WebSocket connection, so a
+/// client cancellation (`stop`/`complete`) for a given id stops just that
+/// stream instead of the whole connection.
+pub(crate) struct SubscriptionTable {
+    tasks: HashMap<String, JoinHandle<()>>,
+}
+
+impl SubscriptionTable {
+    pub(crate) fn new() -> Self {
+        SubscriptionTable {
+            tasks: HashMap::new(),
+        }
+    }
+
+    /// Spawns a task that runs `graphql_runner`'s subscription stream for
+    /// `payload`, pushing each item through `encode_next` onto `tx` until
+    /// the stream ends, then sends one `encode_complete` frame. A cancelled
+    /// task, or one whose send fails, stops without sending any further
+    /// frame. If the stream can't even be started,
+    /// `encode_error` is sent instead, so a client can tell a failed
+    /// subscription from one that finished normally rather than seeing a
+    /// `Complete` either way. Registering a new subscription under an id
+    /// already in use cancels the previous one, matching what a client
+    /// resubscribing with a reused id expects.
+    pub(crate) fn spawn<Q, FNext, FError, FComplete>(
+        &mut self,
+        graphql_runner: Arc<Q>,
+        logger: Logger,
+        id: String,
+        payload: serde_json::Value,
+        tx: mpsc::UnboundedSender<Message>,
+        encode_next: FNext,
+        encode_error: FError,
+        encode_complete: FComplete,
+    ) where
+        Q: GraphQlRunner,
+        FNext: Fn(&str, serde_json::Value) -> Message + Send + 'static,
+        FError: Fn(&str, String) -> Message + Send + 'static,
+        FComplete: Fn(&str) -> Message + Send + 'static,
+    {
+        let task_id = id.clone();
+        let handle = tokio::spawn(async move {
+            match graphql_runner.run_subscription_stream(payload).await {
+                Ok(mut stream) => {
+                    while let Some(item) = stream.next().await {
+                        if tx.send(encode_next(&task_id, item)).is_err() {
+                            return;
+                        }
+                    }
+                    let _ = tx.send(encode_complete(&task_id));
+                }
+                Err(e) => {
+                    warn!(logger, "Subscription execution failed"; "id" => task_id.clone(), "error" => e.to_string());
+                    let _ = tx.send(encode_error(&task_id, e.to_string()));
+                }
+            }
+        });
+
+        if let Some(previous) = self.tasks.insert(id, handle) {
+            previous.abort();
+        }
+    }
+
+    /// Cancels and forgets the subscription registered under `id`, if any.
+    pub(crate) fn cancel(&mut self, id: &str) {
+        if let Some(handle) = self.tasks.remove(id) {
+            handle.abort();
+        }
+    }
+}
+
+impl Drop for SubscriptionTable {
+    fn drop(&mut self) {
+        for (_, handle) in self.tasks.drain() {
+            handle.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_of_unknown_id_is_a_no_op() {
+        let mut table = SubscriptionTable::new();
+        // Must not panic when no subscription is registered under `id`.
+        table.cancel("does-not-exist");
+    }
+}